@@ -5,17 +5,19 @@ extern crate rand;
 extern crate pyo3;
 extern crate memmap;
 extern crate byteorder;
+extern crate zstd;
 
 use rayon::prelude::*;
 use pyo3::prelude::*;
 use pyo3::exceptions;
 use pyo3::types::PyBytes;
 use pyo3::python::Python;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
 use std::cmp;
 use std::mem;
-use std::fs::File;
-use std::io::Cursor;
+use std::fs::{File, OpenOptions};
+use std::io::{Cursor, Write};
+use std::sync::RwLock;
 use byteorder::{ReadBytesExt, LittleEndian};
 use memmap::{MmapOptions, Mmap};
 
@@ -28,6 +30,7 @@ type Position = usize;
 // Start, End, Position, Length
 type Posting = (Seconds, Seconds, Position, usize);
 
+#[derive(Clone, Copy)]
 struct Document {
     base_offset: usize,
 
@@ -77,6 +80,225 @@ fn read_mmap(m: &Mmap, i: usize, n: usize) -> u32 {
     result
 }
 
+#[inline]
+fn write_n(buf: &mut Vec<u8>, value: u32, n: usize) {
+    assert!(n <= mem::size_of::<u32>(), "Cannot write more than u32");
+    for j in 0..n {
+        buf.push(((value >> (j * 8)) & 0xFF) as u8);
+    }
+}
+
+#[inline]
+fn write_u32(buf: &mut Vec<u8>, value: u32) {
+    write_n(buf, value, mem::size_of::<u32>());
+}
+
+// Smallest byte width in [1, 4] whose range covers `max_value`
+#[inline]
+fn min_byte_width(max_value: u64) -> usize {
+    let mut n = 1;
+    while n < mem::size_of::<u32>() && max_value >= (1u64 << (8 * n)) {
+        n += 1;
+    }
+    n
+}
+
+// A compressed, sorted set of DocumentIds, partitioned into 64K-wide chunks the way a
+// Roaring bitmap partitions the 32-bit id space. Each chunk stores its members as sorted,
+// non-adjacent (start, length) runs over the chunk's low 16 bits, so sets can be
+// intersected/unioned/differenced by merging runs, and persist without any decompression
+// step (no sparse-array/bitmap distinction to reconstruct on load).
+#[derive(Clone)]
+struct RoaringBitmap {
+    chunks: Vec<(u16, Vec<(u16, u32)>)>,
+}
+
+// Appends `(start, len)`, coalescing into the previous run when the two touch or overlap
+// (`start` falling anywhere at or before the previous run's end), not just when exactly
+// adjacent — callers like `union_runs` merge two independently-sorted run lists whose runs
+// can genuinely overlap, not merely abut.
+//
+// `len` is `u32`, not `u16`, even though a chunk only spans the low 16 bits of a doc id: a
+// single run can legitimately cover the whole chunk (start 0, len 65536), which doesn't fit
+// in a `u16` — storing it as one would truncate the length to 0 and silently drop every
+// document in a fully-dense chunk.
+#[inline]
+fn push_run(runs: &mut Vec<(u16, u32)>, start: u16, len: u32) {
+    if len == 0 {
+        return;
+    }
+    if let Some(&mut (last_start, ref mut last_len)) = runs.last_mut() {
+        let last_end = last_start as u32 + *last_len;
+        if start as u32 <= last_end {
+            let new_end = start as u32 + len;
+            if new_end > last_end {
+                *last_len = new_end - last_start as u32;
+            }
+            return;
+        }
+    }
+    runs.push((start, len));
+}
+
+fn intersect_runs(a: &[(u16, u32)], b: &[(u16, u32)]) -> Vec<(u16, u32)> {
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let (a_start, a_len) = a[i];
+        let (b_start, b_len) = b[j];
+        let a_end = a_start as u32 + a_len;
+        let b_end = b_start as u32 + b_len;
+        let lo = cmp::max(a_start as u32, b_start as u32);
+        let hi = cmp::min(a_end, b_end);
+        if lo < hi {
+            push_run(&mut result, lo as u16, hi - lo);
+        }
+        if a_end <= b_end { i += 1; } else { j += 1; }
+    }
+    result
+}
+
+fn union_runs(a: &[(u16, u32)], b: &[(u16, u32)]) -> Vec<(u16, u32)> {
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        let (start, len) = if j >= b.len() || (i < a.len() && a[i].0 <= b[j].0) {
+            let r = a[i]; i += 1; r
+        } else {
+            let r = b[j]; j += 1; r
+        };
+        push_run(&mut result, start, len);
+    }
+    result
+}
+
+// a \ b
+fn difference_runs(a: &[(u16, u32)], b: &[(u16, u32)]) -> Vec<(u16, u32)> {
+    let mut result = vec![];
+    for &(a_start, a_len) in a {
+        let a_end = a_start as u32 + a_len;
+        let mut cur = a_start as u32;
+        for &(b_start, b_len) in b {
+            let b_end = b_start as u32 + b_len;
+            if b_end <= cur {
+                continue;
+            }
+            if b_start as u32 >= a_end {
+                break;
+            }
+            if b_start as u32 > cur {
+                push_run(&mut result, cur as u16, cmp::min(b_start as u32, a_end) - cur);
+            }
+            cur = cmp::max(cur, b_end);
+            if cur >= a_end {
+                break;
+            }
+        }
+        if cur < a_end {
+            push_run(&mut result, cur as u16, a_end - cur);
+        }
+    }
+    result
+}
+
+fn and_chunks(a: &[(u16, Vec<(u16, u32)>)], b: &[(u16, Vec<(u16, u32)>)]) ->
+             Vec<(u16, Vec<(u16, u32)>)> {
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i].0 == b[j].0 {
+            let runs = intersect_runs(&a[i].1, &b[j].1);
+            if runs.len() > 0 {
+                result.push((a[i].0, runs));
+            }
+            i += 1; j += 1;
+        } else if a[i].0 < b[j].0 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    result
+}
+
+fn or_chunks(a: &[(u16, Vec<(u16, u32)>)], b: &[(u16, Vec<(u16, u32)>)]) ->
+            Vec<(u16, Vec<(u16, u32)>)> {
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if j >= b.len() || (i < a.len() && a[i].0 < b[j].0) {
+            result.push(a[i].clone()); i += 1;
+        } else if i >= a.len() || b[j].0 < a[i].0 {
+            result.push(b[j].clone()); j += 1;
+        } else {
+            result.push((a[i].0, union_runs(&a[i].1, &b[j].1)));
+            i += 1; j += 1;
+        }
+    }
+    result
+}
+
+fn diff_chunks(a: &[(u16, Vec<(u16, u32)>)], b: &[(u16, Vec<(u16, u32)>)]) ->
+              Vec<(u16, Vec<(u16, u32)>)> {
+    let mut result = vec![];
+    let mut j = 0;
+    for &(key, ref runs) in a {
+        while j < b.len() && b[j].0 < key {
+            j += 1;
+        }
+        if j < b.len() && b[j].0 == key {
+            let diff = difference_runs(runs, &b[j].1);
+            if diff.len() > 0 {
+                result.push((key, diff));
+            }
+        } else {
+            result.push((key, runs.clone()));
+        }
+    }
+    result
+}
+
+impl RoaringBitmap {
+    fn from_sorted_ids(ids: &[DocumentId]) -> RoaringBitmap {
+        let mut chunks: Vec<(u16, Vec<(u16, u32)>)> = vec![];
+        let mut i = 0;
+        while i < ids.len() {
+            let key = (ids[i] >> 16) as u16;
+            let mut runs: Vec<(u16, u32)> = vec![];
+            while i < ids.len() && (ids[i] >> 16) as u16 == key {
+                push_run(&mut runs, (ids[i] & 0xFFFF) as u16, 1);
+                i += 1;
+            }
+            chunks.push((key, runs));
+        }
+        RoaringBitmap { chunks: chunks }
+    }
+
+    fn and(&self, other: &RoaringBitmap) -> RoaringBitmap {
+        RoaringBitmap { chunks: and_chunks(&self.chunks, &other.chunks) }
+    }
+
+    fn or(&self, other: &RoaringBitmap) -> RoaringBitmap {
+        RoaringBitmap { chunks: or_chunks(&self.chunks, &other.chunks) }
+    }
+
+    fn and_not(&self, other: &RoaringBitmap) -> RoaringBitmap {
+        RoaringBitmap { chunks: diff_chunks(&self.chunks, &other.chunks) }
+    }
+
+    fn to_vec(&self) -> Vec<DocumentId> {
+        let mut out = vec![];
+        for &(key, ref runs) in &self.chunks {
+            for &(start, len) in runs {
+                for low in (start as u32)..(start as u32 + len) {
+                    out.push(((key as u32) << 16) | low);
+                }
+            }
+        }
+        out
+    }
+}
+
 fn parse_index(m: &Mmap, datum_size: usize, start_time_size: usize, end_time_size: usize,
                debug: bool) -> BTreeMap<DocumentId, Document> {
     let mut docs = BTreeMap::new();
@@ -126,12 +348,30 @@ fn parse_index(m: &Mmap, datum_size: usize, start_time_size: usize, end_time_siz
     docs
 }
 
-struct _RsCaptionIndex {
+// `docs` and `data` always describe the same on-disk revision, so they're kept behind a
+// single lock rather than two: swapping them independently (even back to back) would leave a
+// window where a reader could pair the new offset map with the stale mmap, or vice versa, and
+// index out of bounds.
+struct IndexState {
     docs: BTreeMap<DocumentId, Document>,
     data: Mmap,
+}
+
+struct _RsCaptionIndex {
+    // Held behind a lock so `add_document` can grow the index after construction. A read
+    // guard is taken and dropped around each individual lookup (`get_doc`/`read_datum`/
+    // `read_time_int`); callers that need to scan every document (the "no doc_ids supplied"
+    // branches below) take one guard just long enough to snapshot `docs` into an owned
+    // `Vec`, then drop it before scanning — they must not hold the guard across the scan,
+    // since the scan calls back into `get_doc`/`read_datum`/etc., each of which takes its
+    // own read guard, and a held outer guard racing a queued writer is a real recursive-read
+    // deadlock with `std::sync::RwLock`, not just a theoretical one.
+    state: RwLock<IndexState>,
     datum_size: usize,
     start_time_size: usize,
     end_time_size: usize,
+    // Path backing `state.data`, reopened by `add_document`/`reload` to grow or refresh the mmap
+    index_file: String,
 }
 
 impl _RsCaptionIndex {
@@ -144,13 +384,26 @@ impl _RsCaptionIndex {
         self.time_int_size() + self.datum_size
     }
 
+    fn get_doc(&self, doc_id: DocumentId) -> Option<Document> {
+        self.state.read().unwrap().docs.get(&doc_id).cloned()
+    }
+
+    // Snapshots every `(DocumentId, Document)` pair under one short-lived read guard, for
+    // callers that need to scan the whole index: `Document` is `Copy`, so this is a cheap
+    // bulk copy, and it lets the scan itself run lock-free instead of holding the guard
+    // across calls that re-lock `state`.
+    fn snapshot_docs(&self) -> Vec<(DocumentId, Document)> {
+        self.state.read().unwrap().docs.iter().map(|(&id, &d)| (id, d)).collect()
+    }
+
     fn read_datum(&self, i: usize) -> u32 {
-        read_mmap(&self.data, i, self.datum_size)
+        read_mmap(&self.state.read().unwrap().data, i, self.datum_size)
     }
 
     fn read_time_int(&self, i: usize) -> (Millis, Millis) {
-        let start = read_mmap(&self.data, i, self.start_time_size);
-        let diff = read_mmap(&self.data, i + self.start_time_size, self.end_time_size);
+        let state = self.state.read().unwrap();
+        let start = read_mmap(&state.data, i, self.start_time_size);
+        let diff = read_mmap(&state.data, i + self.start_time_size, self.end_time_size);
         (start, start + diff)
     }
 
@@ -222,6 +475,71 @@ impl _RsCaptionIndex {
     }
 }
 
+// A position-sorted view over the postings of several alternative tokens occupying the
+// same ngram slot, produced by k-way merging each alternative's (already sorted) postings.
+struct MergedPostingStream<'a> {
+    internal: &'a _RsCaptionIndex,
+    base_index_ofs: usize,
+    posting_size: usize,
+    time_int_size: usize,
+    // (posting_idx, posting_count, read_idx) per alternative token
+    cursors: Vec<(usize, usize, usize)>,
+}
+
+impl<'a> MergedPostingStream<'a> {
+    fn new(internal: &'a _RsCaptionIndex, base_index_ofs: usize, alts: &[(usize, u32)]) -> Self {
+        MergedPostingStream {
+            internal: internal, base_index_ofs: base_index_ofs,
+            posting_size: internal.posting_size(), time_int_size: internal.time_int_size(),
+            cursors: alts.iter().map(|&(idx, n)| (idx, n as usize, 0)).collect()
+        }
+    }
+
+    fn position_at(&self, cursor_idx: usize) -> usize {
+        let (posting_idx, _, read_idx) = self.cursors[cursor_idx];
+        self.internal.read_datum(
+            self.base_index_ofs + (posting_idx + read_idx) * self.posting_size +
+            self.time_int_size) as usize
+    }
+
+    fn time_int_at(&self, cursor_idx: usize) -> (Millis, Millis) {
+        let (posting_idx, _, read_idx) = self.cursors[cursor_idx];
+        self.internal.read_time_int(self.base_index_ofs + (posting_idx + read_idx) * self.posting_size)
+    }
+
+    // Smallest remaining position across all alternatives' cursors, or None if exhausted.
+    fn min_position(&self) -> Option<usize> {
+        let mut min_pos = None;
+        for i in 0..self.cursors.len() {
+            let (_, count, read_idx) = self.cursors[i];
+            if read_idx < count {
+                let pos = self.position_at(i);
+                min_pos = Some(match min_pos {
+                    Some(p) if p <= pos => p,
+                    _ => pos
+                });
+            }
+        }
+        min_pos
+    }
+
+    // Advances every cursor sitting at `pos` (deduping equal positions across alternatives),
+    // returning the time interval of the first alternative found at `pos`.
+    fn consume(&mut self, pos: usize) -> (Millis, Millis) {
+        let mut time_int = None;
+        for i in 0..self.cursors.len() {
+            let (_, count, read_idx) = self.cursors[i];
+            if read_idx < count && self.position_at(i) == pos {
+                if time_int.is_none() {
+                    time_int = Some(self.time_int_at(i));
+                }
+                self.cursors[i].2 += 1;
+            }
+        }
+        time_int.unwrap()
+    }
+}
+
 #[pyclass]
 struct RsCaptionIndex {
     _internal: _RsCaptionIndex,
@@ -232,11 +550,11 @@ struct RsCaptionIndex {
 impl RsCaptionIndex {
 
     fn document_exists(&self, doc_id: DocumentId) -> PyResult<bool> {
-        Ok(self._internal.docs.get(&doc_id).is_some())
+        Ok(self._internal.get_doc(doc_id).is_some())
     }
 
     fn document_length(&self, doc_id: DocumentId) -> PyResult<(usize, f32)> {
-        match self._internal.docs.get(&doc_id) {
+        match self._internal.get_doc(doc_id) {
             Some(d) => Ok((d.length, ms_to_s(d.duration))),
             None => Err(exceptions::ValueError::py_err("Document not found"))
         }
@@ -259,16 +577,17 @@ impl RsCaptionIndex {
             if doc_ids.len() > 0 {
                 doc_ids.par_sort();
                 doc_ids.par_iter().filter_map(
-                    |id| match self._internal.docs.get(&id) {
+                    |id| match self._internal.get_doc(*id) {
                         None => None,
-                        Some(d) => match lookup_and_read_postings(d) {
+                        Some(ref d) => match lookup_and_read_postings(d) {
                             None => None,
                             Some(p) => Some((*id, p))
                         }
                     }
                 ).collect()
             } else {
-                self._internal.docs.par_iter().filter_map(
+                let docs = self._internal.snapshot_docs();
+                docs.par_iter().filter_map(
                     |(id, d)| match lookup_and_read_postings(d) {
                         None => None,
                         Some(p) => Some((*id, p))
@@ -289,13 +608,14 @@ impl RsCaptionIndex {
         let docs_w_token =
             if doc_ids.len() > 0 {
                 doc_ids.par_iter().filter_map(
-                    |id| match self._internal.docs.get(&id) {
+                    |id| match self._internal.get_doc(*id) {
                         None => None,
-                        Some(d) => if has_unigram(d) {Some(*id)} else {None}
+                        Some(ref d) => if has_unigram(d) {Some(*id)} else {None}
                     }
                 ).collect()
             } else {
-                self._internal.docs.par_iter().filter_map(
+                let docs = self._internal.snapshot_docs();
+                docs.par_iter().filter_map(
                     |(id, d)| if has_unigram(d) {Some(*id)} else {None}
                 ).collect()
             };
@@ -396,16 +716,17 @@ impl RsCaptionIndex {
                 if doc_ids.len() > 0 {
                     doc_ids.par_sort();
                     doc_ids.par_iter().filter_map(
-                        |id| match self._internal.docs.get(&id) {
+                        |id| match self._internal.get_doc(*id) {
                             None => None,
-                            Some(d) => match load_ngrams(d) {
+                            Some(ref d) => match load_ngrams(d) {
                                 None => None,
                                 Some(p) => Some((*id, p))
                             }
                         }
                     ).collect()
                 } else {
-                    self._internal.docs.par_iter().filter_map(
+                    let docs = self._internal.snapshot_docs();
+                    docs.par_iter().filter_map(
                         |(id, d)| match load_ngrams(d) {
                             None => None,
                             Some(p) => Some((*id, p))
@@ -416,6 +737,343 @@ impl RsCaptionIndex {
         }
     }
 
+    // Like `ngram_search`, but each slot accepts any one of a set of alternative tokens
+    // (e.g. spelling variants within an edit distance, or synonyms) rather than a single
+    // fixed token. A slot with no alternatives present in a document rules the whole
+    // document out; an empty alternative set anywhere in `ngram` can never match.
+    fn ngram_search_alts(&self, ngram: Vec<Vec<TokenId>>, mut doc_ids: Vec<DocumentId>) ->
+                        PyResult<Vec<(DocumentId, Vec<Posting>)>> {
+        if ngram.len() == 0 {
+            return Err(exceptions::ValueError::py_err("Ngram cannot be empty"));
+        } else if ngram.iter().any(|alts| alts.len() == 0) {
+            return Ok(vec![]);
+        } else if ngram.len() == 1 && ngram[0].len() == 1 {
+            return self.unigram_search(ngram[0][0], doc_ids);
+        }
+        if self.debug {
+            let len_str = doc_ids.len().to_string();
+            eprintln!("ngram search alts: {:?} in {} documents", ngram,
+                      if doc_ids.len() > 0 {len_str.as_str()} else {"all"});
+        }
+
+        let load_ngrams = |d: &Document| -> Option<Vec<Posting>> {
+            let base_index_ofs = d.base_offset + d.inv_index_offset;
+
+            let mut streams: Vec<MergedPostingStream> = Vec::with_capacity(ngram.len());
+            for alts in &ngram {
+                let postings: Vec<(usize, u32)> = alts.iter().filter_map(
+                    |&tok| self._internal.lookup_postings(d, tok)
+                ).collect();
+                if postings.len() == 0 {
+                    return None; // None of this slot's alternatives occur in the document
+                }
+                streams.push(MergedPostingStream::new(&self._internal, base_index_ofs, &postings));
+            }
+
+            let mut result: Vec<Posting> = vec![];
+            'slot_0_loop: while let Some(pos_0) = streams[0].min_position() {
+                let time_int_0 = streams[0].consume(pos_0);
+                let mut last_time_int = time_int_0;
+                let mut matched = true;
+
+                for j in 1..streams.len() {
+                    let target_pos = pos_0 + j;
+                    loop {
+                        match streams[j].min_position() {
+                            None => break 'slot_0_loop, // Slot j's alternatives are exhausted
+                            Some(p) if p == target_pos => {
+                                last_time_int = streams[j].consume(p);
+                                break;
+                            },
+                            Some(p) if p < target_pos => { streams[j].consume(p); },
+                            Some(_) => { matched = false; break; }
+                        }
+                    }
+                    if !matched {
+                        break;
+                    }
+                }
+
+                if matched {
+                    result.push((
+                        ms_to_s(time_int_0.0), ms_to_s(last_time_int.1), pos_0, ngram.len()
+                    ));
+                }
+            }
+            if result.len() > 0 {
+                Some(result)
+            } else {
+                None
+            }
+        };
+        let docs_to_ngrams =
+            if doc_ids.len() > 0 {
+                doc_ids.par_sort();
+                doc_ids.par_iter().filter_map(
+                    |id| match self._internal.get_doc(*id) {
+                        None => None,
+                        Some(ref d) => match load_ngrams(d) {
+                            None => None,
+                            Some(p) => Some((*id, p))
+                        }
+                    }
+                ).collect()
+            } else {
+                let docs = self._internal.snapshot_docs();
+                docs.par_iter().filter_map(
+                    |(id, d)| match load_ngrams(d) {
+                        None => None,
+                        Some(p) => Some((*id, p))
+                    }
+                ).collect()
+            };
+        Ok(docs_to_ngrams)
+    }
+
+    // Finds documents where `tokens` co-occur near each other rather than as a strict
+    // contiguous phrase. When `ordered` is set, tokens must appear in the given sequence,
+    // each within `max_gap` positions after the previous matched token; otherwise the
+    // nearest occurrences of all tokens, in any arrangement, must span at most `max_gap`
+    // positions.
+    fn proximity_search(&self, tokens: Vec<TokenId>, max_gap: usize, ordered: bool) ->
+                        PyResult<Vec<(DocumentId, Vec<Posting>)>> {
+        if tokens.len() == 0 {
+            return Err(exceptions::ValueError::py_err("Tokens cannot be empty"));
+        } else if tokens.len() == 1 {
+            return self.unigram_search(tokens[0], vec![]);
+        }
+        if self.debug {
+            eprintln!("proximity search: {:?} within {} ({})", tokens, max_gap,
+                      if ordered {"ordered"} else {"unordered"});
+        }
+        let time_int_size = self._internal.time_int_size();
+        let posting_size = self._internal.posting_size();
+
+        let find_matches = |d: &Document| -> Option<Vec<Posting>> {
+            let base_index_ofs = d.base_offset + d.inv_index_offset;
+
+            let mut posting_offsets = Vec::with_capacity(tokens.len());
+            for i in 0..tokens.len() {
+                match self._internal.lookup_postings(d, tokens[i]) {
+                    None => return None, // One of the tokens is not found
+                    Some(p) => posting_offsets.push(p)
+                }
+            }
+
+            let read_pos = |posting_idx: usize, read_idx: usize| -> usize {
+                self._internal.read_datum(
+                    base_index_ofs + (posting_idx + read_idx) * posting_size +
+                    time_int_size) as usize
+            };
+            let read_int = |posting_idx: usize, read_idx: usize| -> (Millis, Millis) {
+                self._internal.read_time_int(base_index_ofs + (posting_idx + read_idx) * posting_size)
+            };
+
+            let mut result: Vec<Posting> = vec![];
+            let mut token_j_read_idx = vec![0usize; tokens.len() - 1];
+
+            let token_0_posting_idx = posting_offsets[0].0;
+            let token_0_posting_count = posting_offsets[0].1 as usize;
+
+            'token_0_loop: for i in 0..token_0_posting_count {
+                let pos_0 = read_pos(token_0_posting_idx, i);
+                let time_int_0 = read_int(token_0_posting_idx, i);
+
+                let mut min_pos = pos_0;
+                let mut max_pos = pos_0;
+                let mut min_start = time_int_0.0;
+                let mut max_end = time_int_0.1;
+
+                if ordered {
+                    let mut prev_pos = pos_0;
+                    let mut matched = true;
+                    'token_j_loop: for j in 1..tokens.len() {
+                        let token_j_posting_idx = posting_offsets[j].0;
+                        let token_j_posting_count = posting_offsets[j].1 as usize;
+                        let pos_j;
+                        loop {
+                            let candidate = read_pos(token_j_posting_idx, token_j_read_idx[j - 1]);
+                            if candidate > prev_pos {
+                                if candidate <= prev_pos + max_gap {
+                                    pos_j = candidate;
+                                    break;
+                                } else {
+                                    matched = false;
+                                    break 'token_j_loop;
+                                }
+                            } else {
+                                token_j_read_idx[j - 1] += 1;
+                                if token_j_read_idx[j - 1] == token_j_posting_count {
+                                    break 'token_0_loop;
+                                }
+                            }
+                        }
+                        let time_int_j = read_int(token_j_posting_idx, token_j_read_idx[j - 1]);
+                        prev_pos = pos_j;
+                        max_pos = pos_j;
+                        max_end = time_int_j.1;
+                    }
+                    if matched {
+                        result.push((ms_to_s(min_start), ms_to_s(max_end), min_pos,
+                                      max_pos - min_pos + 1));
+                    }
+                } else {
+                    for j in 1..tokens.len() {
+                        let token_j_posting_idx = posting_offsets[j].0;
+                        let token_j_posting_count = posting_offsets[j].1 as usize;
+
+                        // Advance this token's cursor forward only while doing so does not
+                        // increase its distance to pos_0 (nearest occurrence, monotonic scan)
+                        loop {
+                            let read_idx = token_j_read_idx[j - 1];
+                            if read_idx + 1 >= token_j_posting_count {
+                                break;
+                            }
+                            let cur_pos = read_pos(token_j_posting_idx, read_idx) as i64;
+                            let next_pos = read_pos(token_j_posting_idx, read_idx + 1) as i64;
+                            let p0 = pos_0 as i64;
+                            if (next_pos - p0).abs() <= (cur_pos - p0).abs() {
+                                token_j_read_idx[j - 1] += 1;
+                            } else {
+                                break;
+                            }
+                        }
+                        let pos_j = read_pos(token_j_posting_idx, token_j_read_idx[j - 1]);
+                        let time_int_j = read_int(token_j_posting_idx, token_j_read_idx[j - 1]);
+                        if pos_j < min_pos {
+                            min_pos = pos_j;
+                            min_start = time_int_j.0;
+                        }
+                        if pos_j > max_pos {
+                            max_pos = pos_j;
+                            max_end = time_int_j.1;
+                        }
+                    }
+                    if max_pos - min_pos <= max_gap {
+                        result.push((ms_to_s(min_start), ms_to_s(max_end), min_pos,
+                                      max_pos - min_pos + 1));
+                    }
+                }
+            }
+            if result.len() > 0 {
+                Some(result)
+            } else {
+                None
+            }
+        };
+
+        let docs = self._internal.snapshot_docs();
+        Ok(docs.par_iter().filter_map(
+            |(id, d)| match find_matches(d) {
+                None => None,
+                Some(p) => Some((*id, p))
+            }
+        ).collect())
+    }
+
+    // Finds an ordered sequence of tokens occurring within `max_gap` positions of each
+    // other — real phrase lookup over the stored token positions, rather than the strict
+    // contiguity `ngram_search` requires. A document is ruled out as soon as any token in
+    // the phrase is entirely absent from it (the document-set intersection); within a
+    // surviving document each subsequent token is found by binary-searching its sorted
+    // position list for the first occurrence strictly after the previous match, which also
+    // keeps repeated tokens in the phrase from matching the same position twice.
+    fn phrase(&self, tokens: Vec<TokenId>, max_gap: usize) ->
+             PyResult<Vec<(DocumentId, Vec<Posting>)>> {
+        if tokens.len() == 0 {
+            return Err(exceptions::ValueError::py_err("Tokens cannot be empty"));
+        } else if tokens.len() == 1 {
+            return self.unigram_search(tokens[0], vec![]);
+        }
+        if self.debug {
+            eprintln!("phrase: {:?} within {} positions of each other", tokens, max_gap);
+        }
+        let time_int_size = self._internal.time_int_size();
+        let posting_size = self._internal.posting_size();
+
+        let find_phrase = |d: &Document| -> Option<Vec<Posting>> {
+            let base_index_ofs = d.base_offset + d.inv_index_offset;
+
+            // Intersect the document sets of every token up front: if any is missing, this
+            // document can never contain the phrase
+            let mut posting_offsets = Vec::with_capacity(tokens.len());
+            for &tok in &tokens {
+                match self._internal.lookup_postings(d, tok) {
+                    None => return None,
+                    Some(p) => posting_offsets.push(p)
+                }
+            }
+
+            let read_pos = |posting_idx: usize, i: usize| -> usize {
+                self._internal.read_datum(
+                    base_index_ofs + (posting_idx + i) * posting_size + time_int_size) as usize
+            };
+            let read_int = |posting_idx: usize, i: usize| -> (Millis, Millis) {
+                self._internal.read_time_int(base_index_ofs + (posting_idx + i) * posting_size)
+            };
+
+            // First position strictly after `after` in token j's sorted position list
+            let first_after = |posting_idx: usize, count: usize, after: usize| -> Option<usize> {
+                let mut lo = 0;
+                let mut hi = count;
+                while lo < hi {
+                    let mid = (lo + hi) / 2;
+                    if read_pos(posting_idx, mid) <= after {
+                        lo = mid + 1;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                if lo < count { Some(lo) } else { None }
+            };
+
+            let mut result = vec![];
+            let (token_0_idx, token_0_count) = posting_offsets[0];
+            for i in 0..token_0_count {
+                let pos_0 = read_pos(token_0_idx, i);
+                let time_int_0 = read_int(token_0_idx, i);
+
+                let mut prev_pos = pos_0;
+                let mut last_time_int = time_int_0;
+                let mut matched = true;
+                for j in 1..tokens.len() {
+                    let (idx_j, count_j) = posting_offsets[j];
+                    match first_after(idx_j, count_j, prev_pos) {
+                        Some(read_idx) => {
+                            let candidate = read_pos(idx_j, read_idx);
+                            if candidate <= prev_pos + max_gap + 1 {
+                                prev_pos = candidate;
+                                last_time_int = read_int(idx_j, read_idx);
+                            } else {
+                                matched = false;
+                                break;
+                            }
+                        },
+                        None => { matched = false; break; }
+                    }
+                }
+                if matched {
+                    result.push((
+                        ms_to_s(time_int_0.0), ms_to_s(last_time_int.1), pos_0,
+                        prev_pos - pos_0 + 1));
+                }
+            }
+            if result.len() > 0 {
+                Some(result)
+            } else {
+                None
+            }
+        };
+
+        let docs = self._internal.snapshot_docs();
+        Ok(docs.par_iter().filter_map(
+            |(id, d)| match find_phrase(d) {
+                None => None,
+                Some(p) => Some((*id, p))
+            }
+        ).collect())
+    }
+
     fn ngram_contains(&self, ngram: Vec<TokenId>, doc_ids: Vec<DocumentId>) ->
                       PyResult<Vec<DocumentId>> {
         if ngram.len() == 0 {
@@ -494,13 +1152,14 @@ impl RsCaptionIndex {
             let docs_w_ngram =
                 if doc_ids.len() > 0 {
                     doc_ids.par_iter().filter_map(
-                        |id| match self._internal.docs.get(&id) {
+                        |id| match self._internal.get_doc(*id) {
                              None => None,
-                             Some(d) => if has_ngram(d) {Some(*id)} else {None}
+                             Some(ref d) => if has_ngram(d) {Some(*id)} else {None}
                         }
                     ).collect()
                 } else {
-                    self._internal.docs.par_iter().filter_map(
+                    let docs = self._internal.snapshot_docs();
+                    docs.par_iter().filter_map(
                         |(id, d)| if has_ngram(d) {Some(*id)} else {None}
                     ).collect()
                 };
@@ -508,11 +1167,57 @@ impl RsCaptionIndex {
         }
     }
 
+    // Returns the top-`k` documents for `query` ordered by BM25 relevance, rather than the
+    // unordered match lists `unigram_search`/`ngram_search` produce. `k1` and `b` are the
+    // usual BM25 term-frequency saturation and length-normalization parameters.
+    fn ranked_search(&self, query: Vec<TokenId>, k: usize, k1: f32, b: f32) ->
+                     PyResult<Vec<(DocumentId, f32)>> {
+        if query.len() == 0 {
+            return Err(exceptions::ValueError::py_err("Query cannot be empty"));
+        }
+        if self.debug {
+            eprintln!("ranked search: {:?} top {}", query, k);
+        }
+        let docs = self._internal.snapshot_docs();
+        let n_docs = docs.len() as f32;
+        let avgdl = docs.iter().map(|&(_, d)| d.length as f32).sum::<f32>() / n_docs;
+
+        // idf per query term: ln(1 + (N - df + 0.5) / (df + 0.5))
+        let term_idfs: Vec<f32> = query.iter().map(|&term| {
+            let df = docs.par_iter().filter(
+                |&(_, d)| self._internal.lookup_postings(d, term).is_some()
+            ).count() as f32;
+            (1. + (n_docs - df + 0.5) / (df + 0.5)).ln()
+        }).collect();
+
+        let mut scores: Vec<(DocumentId, f32)> = docs.par_iter().filter_map(
+            |(id, d)| {
+                let mut score = 0.;
+                let mut matched = false;
+                let l = d.length as f32;
+                for (i, &term) in query.iter().enumerate() {
+                    if let Some((_, n)) = self._internal.lookup_postings(d, term) {
+                        matched = true;
+                        let tf = n as f32;
+                        score += term_idfs[i] * (tf * (k1 + 1.)) /
+                            (tf + k1 * (1. - b + b * l / avgdl));
+                    }
+                }
+                if matched { Some((*id, score)) } else { None }
+            }
+        ).collect();
+
+        scores.par_sort_unstable_by(
+            |a, b| b.1.partial_cmp(&a.1).unwrap_or(cmp::Ordering::Equal));
+        scores.truncate(k);
+        Ok(scores)
+    }
+
     fn tokens(&self, doc_id: DocumentId, position: usize, n: usize) -> PyResult<Vec<TokenId>> {
         if self.debug {
             eprintln!("tokens: {}+{} in {}", position, n, doc_id);
         }
-        match self._internal.docs.get(&doc_id) {
+        match self._internal.get_doc(doc_id) {
             Some(d) => {
                 let min_pos = cmp::min(position, d.length);
                 let max_pos = cmp::min(position + n, d.length);
@@ -539,8 +1244,8 @@ impl RsCaptionIndex {
         let start_ms = if start > 0. {s_to_ms(start)} else {0};
         let posting_size = self._internal.posting_size();
         let time_int_size = self._internal.time_int_size();
-        match self._internal.docs.get(&doc_id) {
-            Some(d) => {
+        match self._internal.get_doc(doc_id) {
+            Some(ref d) => {
                 let mut locations = vec![];
                 let mut start_idx = self._internal.lookup_time_int(d, start_ms);
                 if start_idx > 0 {
@@ -576,8 +1281,8 @@ impl RsCaptionIndex {
         if self.debug {
             eprintln!("position: {}s in {}", time, doc_id);
         }
-        match self._internal.docs.get(&doc_id) {
-            Some(d) => Ok({
+        match self._internal.get_doc(doc_id) {
+            Some(ref d) => Ok({
                 let idx = self._internal.lookup_time_int(d, s_to_ms(time));
                 let ofs = d.base_offset + d.time_index_offset +
                     idx * self._internal.posting_size() + self._internal.time_int_size();
@@ -597,8 +1302,10 @@ impl RsCaptionIndex {
                 let docs = parse_index(&m, datum_size, start_time_size, end_time_size, debug);
                 RsCaptionIndex {
                     _internal: _RsCaptionIndex {
-                        docs: docs, data: m, datum_size: datum_size,
-                        start_time_size: start_time_size, end_time_size: end_time_size
+                        state: RwLock::new(IndexState { docs: docs, data: m }),
+                        datum_size: datum_size,
+                        start_time_size: start_time_size, end_time_size: end_time_size,
+                        index_file: index_file
                     },
                     debug: debug
                 }
@@ -606,16 +1313,795 @@ impl RsCaptionIndex {
             Err(s) => Err(exceptions::Exception::py_err(s.to_string()))
         }
     }
+
+    // Appends `lines` as a new document to the backing file and refreshes the in-memory
+    // index so it becomes immediately queryable, without requiring callers to rebuild the
+    // whole index from scratch.
+    fn add_document(&self, doc_id: DocumentId, duration: Millis,
+                    lines: Vec<(Millis, Millis, Vec<TokenId>)>) -> PyResult<()> {
+        let pending = PendingDocument { doc_id: doc_id, duration: duration, lines: lines };
+        RsCaptionIndex::validate_widths(
+            &pending, self._internal.datum_size, self._internal.start_time_size,
+            self._internal.end_time_size)?;
+        let mut buf = vec![];
+        RsCaptionIndexBuilder::write_document(
+            &mut buf, &pending, self._internal.datum_size,
+            self._internal.start_time_size, self._internal.end_time_size)?;
+        let mut f = OpenOptions::new().append(true).open(&self._internal.index_file)?;
+        f.write_all(&buf)?;
+        self.reload_from_disk()
+    }
+
+    // Drops the cached parsed state and re-maps the backing file, picking up any documents
+    // an external writer appended since construction (or since the last `reload`).
+    fn reload(&self) -> PyResult<()> {
+        self.reload_from_disk()
+    }
+
+    // Alias for `reload` — some callers think of this as invalidating a stale cache rather
+    // than as refreshing it, so both names are exposed for the same operation.
+    fn invalidate(&self) -> PyResult<()> {
+        self.reload_from_disk()
+    }
+}
+
+impl RsCaptionIndex {
+    // `write_n` silently truncates a value that doesn't fit the field width it's packed
+    // into, so an appended document whose token id, position, or timestamp overflows the
+    // widths this index was built with would otherwise be written out corrupted with no
+    // error. Mirrors the max-value scan `RsCaptionIndexBuilder::finish` uses to pick widths
+    // in the first place.
+    fn validate_widths(doc: &PendingDocument, datum_size: usize, start_time_size: usize,
+                       end_time_size: usize) -> PyResult<()> {
+        let mut max_datum: u64 = 0;
+        let mut max_start: u64 = 0;
+        let mut max_end_delta: u64 = 0;
+        let mut length: u64 = 0;
+        for &(start, end, ref line_tokens) in &doc.lines {
+            if end < start {
+                return Err(exceptions::ValueError::py_err("Line end precedes start"));
+            }
+            max_start = cmp::max(max_start, start as u64);
+            max_end_delta = cmp::max(max_end_delta, (end - start) as u64);
+            for &tok in line_tokens {
+                max_datum = cmp::max(max_datum, tok as u64);
+            }
+            length += line_tokens.len() as u64;
+        }
+        max_datum = cmp::max(max_datum, length);
+        if min_byte_width(max_datum) > datum_size {
+            return Err(exceptions::ValueError::py_err(
+                "Token id, position, or document length exceeds this index's datum_size"));
+        }
+        if min_byte_width(max_start) > start_time_size {
+            return Err(exceptions::ValueError::py_err(
+                "Start time exceeds this index's start_time_size"));
+        }
+        if min_byte_width(max_end_delta) > end_time_size {
+            return Err(exceptions::ValueError::py_err(
+                "Line duration exceeds this index's end_time_size"));
+        }
+        Ok(())
+    }
+
+    fn reload_from_disk(&self) -> PyResult<()> {
+        let mmap = MmapOptions::new().map(&File::open(&self._internal.index_file)?);
+        match mmap {
+            Ok(m) => {
+                let docs = parse_index(
+                    &m, self._internal.datum_size, self._internal.start_time_size,
+                    self._internal.end_time_size, self.debug);
+                // Swap both under the same write guard so a concurrent reader can never
+                // observe the new offset map paired with the stale mmap (or vice versa).
+                *self._internal.state.write().unwrap() = IndexState { docs: docs, data: m };
+                Ok(())
+            },
+            Err(s) => Err(exceptions::Exception::py_err(s.to_string()))
+        }
+    }
+}
+
+// A document staged in an `RsCaptionIndexBuilder`, described as a sequence of timed lines
+// (the same shape a caption/subtitle file naturally has) rather than raw postings.
+struct PendingDocument {
+    doc_id: DocumentId,
+    duration: Millis,
+    lines: Vec<(Millis, Millis, Vec<TokenId>)>,
+}
+
+// Writes the exact binary layout `parse_index` consumes, so an index can be built in Rust
+// without the Python encoder. Documents are staged with `add_document` and serialized in
+// one pass by `finish`, which also auto-selects the minimum field widths that hold the
+// largest value seen so far (token id, document length, timestamp) and returns them for
+// constructing the matching `RsCaptionIndex`.
+#[pyclass]
+struct RsCaptionIndexBuilder {
+    docs: Vec<PendingDocument>,
+}
+
+impl RsCaptionIndexBuilder {
+    fn write_document(buf: &mut Vec<u8>, doc: &PendingDocument,
+                      datum_size: usize, start_time_size: usize, end_time_size: usize)
+                      -> PyResult<()> {
+        let token_entry_size = 2 * datum_size;
+        let posting_size = datum_size + start_time_size + end_time_size;
+        let time_int_entry_size = posting_size;
+
+        // Flatten the lines into the raw token stream, per-line time spans, and the
+        // per-token occurrence lists (already position-sorted, built left to right)
+        let mut tokens: Vec<TokenId> = vec![];
+        let mut time_ints: Vec<(Millis, Millis, usize)> = Vec::with_capacity(doc.lines.len());
+        let mut postings_by_token: BTreeMap<TokenId, Vec<(usize, Millis, Millis)>> = BTreeMap::new();
+        for &(start, end, ref line_tokens) in &doc.lines {
+            if end < start {
+                return Err(exceptions::ValueError::py_err("Line end precedes start"));
+            }
+            let pos0 = tokens.len();
+            time_ints.push((start, end, pos0));
+            for (i, &tok) in line_tokens.iter().enumerate() {
+                postings_by_token.entry(tok).or_insert_with(Vec::new).push((pos0 + i, start, end));
+            }
+            tokens.extend(line_tokens.iter().cloned());
+        }
+        let length = tokens.len();
+        let unique_token_count = postings_by_token.len() as u32;
+        let posting_count = length as u32;
+        let time_int_count = time_ints.len() as u32;
+
+        let base_offset = buf.len();
+        write_u32(buf, doc.doc_id);
+        write_u32(buf, doc.duration);
+        write_u32(buf, unique_token_count);
+        write_u32(buf, posting_count);
+        write_u32(buf, time_int_count);
+        write_u32(buf, length as u32);
+
+        // Lexicon: (token, posting_start_index), ascending by token
+        let mut posting_start = 0u32;
+        let mut postings_flat: Vec<(usize, Millis, Millis)> = Vec::with_capacity(length);
+        for (&token, occurrences) in &postings_by_token {
+            write_n(buf, token, datum_size);
+            write_n(buf, posting_start, datum_size);
+            posting_start += occurrences.len() as u32;
+            postings_flat.extend(occurrences.iter().cloned());
+        }
+
+        // Inverted index: postings grouped by token, sorted by position within each group
+        for &(pos, start, end) in &postings_flat {
+            write_n(buf, start, start_time_size);
+            write_n(buf, end - start, end_time_size);
+            write_n(buf, pos as u32, datum_size);
+        }
+
+        // Time interval index
+        for &(start, end, pos) in &time_ints {
+            write_n(buf, start, start_time_size);
+            write_n(buf, end - start, end_time_size);
+            write_n(buf, pos as u32, datum_size);
+        }
+
+        // Raw token stream
+        for &tok in &tokens {
+            write_n(buf, tok, datum_size);
+        }
+
+        let written = buf.len() - base_offset;
+        let expected = 6 * mem::size_of::<u32>()
+            + (unique_token_count as usize) * token_entry_size
+            + (posting_count as usize) * posting_size
+            + (time_int_count as usize) * time_int_entry_size
+            + length * datum_size;
+        assert!(written == expected, "Builder byte cursor does not match parse_index's layout");
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl RsCaptionIndexBuilder {
+
+    // Stages a document as a sequence of `(start_ms, end_ms, tokens)` lines, mirroring how
+    // a caption file is naturally read: each line's timing is shared by all of its tokens.
+    fn add_document(&mut self, doc_id: DocumentId, duration: Millis,
+                    lines: Vec<(Millis, Millis, Vec<TokenId>)>) -> PyResult<()> {
+        self.docs.push(PendingDocument { doc_id: doc_id, duration: duration, lines: lines });
+        Ok(())
+    }
+
+    // Serializes every staged document to `index_file` and returns the
+    // `(datum_size, start_time_size, end_time_size)` the result must be loaded with.
+    fn finish(&self, index_file: String) -> PyResult<(usize, usize, usize)> {
+        let mut max_datum: u64 = 0;
+        let mut max_start: u64 = 0;
+        let mut max_end_delta: u64 = 0;
+        for doc in &self.docs {
+            let mut length: u64 = 0;
+            for &(start, end, ref line_tokens) in &doc.lines {
+                if end < start {
+                    return Err(exceptions::ValueError::py_err("Line end precedes start"));
+                }
+                max_start = cmp::max(max_start, start as u64);
+                max_end_delta = cmp::max(max_end_delta, (end - start) as u64);
+                for &tok in line_tokens {
+                    max_datum = cmp::max(max_datum, tok as u64);
+                }
+                length += line_tokens.len() as u64;
+            }
+            // Covers both the document length and every posting_start_index/position we'll write
+            max_datum = cmp::max(max_datum, length);
+        }
+
+        let datum_size = min_byte_width(max_datum);
+        let start_time_size = min_byte_width(max_start);
+        let end_time_size = min_byte_width(max_end_delta);
+
+        let mut buf: Vec<u8> = vec![];
+        for doc in &self.docs {
+            RsCaptionIndexBuilder::write_document(
+                &mut buf, doc, datum_size, start_time_size, end_time_size)?;
+        }
+        File::create(&index_file)?.write_all(&buf)?;
+
+        Ok((datum_size, start_time_size, end_time_size))
+    }
+
+    #[new]
+    unsafe fn __new__(obj: &PyRawObject) -> PyResult<()> {
+        obj.init(|_| RsCaptionIndexBuilder { docs: vec![] });
+        Ok(())
+    }
+}
+
+// Stages a `RoaringBitmap` of DocumentIds per TokenId and serializes them into the
+// directory + data layout `RsTermSets` loads: a `token_count`-u32 header, then one
+// `(token, data_offset, num_chunks)` directory entry per term ascending by TokenId, then
+// each bitmap's chunks back to back as `(key, num_runs, (start, len)*)`.
+#[pyclass]
+struct RsTermSetsBuilder {
+    terms: BTreeMap<TokenId, Vec<DocumentId>>,
+}
+
+#[pymethods]
+impl RsTermSetsBuilder {
+
+    fn add_term(&mut self, token: TokenId, mut doc_ids: Vec<DocumentId>) -> PyResult<()> {
+        doc_ids.par_sort();
+        doc_ids.dedup();
+        self.terms.insert(token, doc_ids);
+        Ok(())
+    }
+
+    fn finish(&self, bitmap_file: String) -> PyResult<()> {
+        let directory_size = mem::size_of::<u32>() + self.terms.len() * 3 * mem::size_of::<u32>();
+        let mut directory = Vec::with_capacity(self.terms.len());
+        let mut data = vec![];
+        for (&token, doc_ids) in &self.terms {
+            let bitmap = RoaringBitmap::from_sorted_ids(doc_ids);
+            let offset = directory_size + data.len();
+            for &(key, ref runs) in &bitmap.chunks {
+                write_u32(&mut data, key as u32);
+                write_u32(&mut data, runs.len() as u32);
+                for &(start, len) in runs {
+                    write_u32(&mut data, start as u32);
+                    write_u32(&mut data, len);
+                }
+            }
+            directory.push((token, offset as u32, bitmap.chunks.len() as u32));
+        }
+
+        let mut buf = Vec::with_capacity(directory_size + data.len());
+        write_u32(&mut buf, self.terms.len() as u32);
+        for (token, offset, num_chunks) in directory {
+            write_u32(&mut buf, token);
+            write_u32(&mut buf, offset);
+            write_u32(&mut buf, num_chunks);
+        }
+        buf.extend(data);
+        File::create(&bitmap_file)?.write_all(&buf)?;
+        Ok(())
+    }
+
+    #[new]
+    unsafe fn __new__(obj: &PyRawObject) -> PyResult<()> {
+        obj.init(|_| RsTermSetsBuilder { terms: BTreeMap::new() });
+        Ok(())
+    }
+}
+
+struct TermSetEntry {
+    offset: usize,
+    num_chunks: usize,
+}
+
+fn read_bitmap_chunks(m: &Mmap, offset: usize, num_chunks: usize) -> Vec<(u16, Vec<(u16, u32)>)> {
+    let mut chunks = Vec::with_capacity(num_chunks);
+    let mut ofs = offset;
+    for _ in 0..num_chunks {
+        let key = read_mmap_u32(m, ofs) as u16;
+        let num_runs = read_mmap_u32(m, ofs + 4) as usize;
+        ofs += 8;
+        let mut runs = Vec::with_capacity(num_runs);
+        for _ in 0..num_runs {
+            let start = read_mmap_u32(m, ofs) as u16;
+            let len = read_mmap_u32(m, ofs + 4);
+            runs.push((start, len));
+            ofs += 8;
+        }
+        chunks.push((key, runs));
+    }
+    chunks
+}
+
+// Loads the per-token `RoaringBitmap`s written by `RsTermSetsBuilder` and evaluates
+// boolean queries directly on them, resolving candidate documents without touching any
+// positional postings.
+#[pyclass]
+struct RsTermSets {
+    terms: BTreeMap<TokenId, TermSetEntry>,
+    data: Mmap,
+    // Union of every indexed term's documents, used as the universe for `documents_without`
+    // when the caller doesn't supply one
+    universe: RoaringBitmap,
+    debug: bool,
+}
+
+impl RsTermSets {
+    fn read_bitmap(&self, entry: &TermSetEntry) -> RoaringBitmap {
+        RoaringBitmap { chunks: read_bitmap_chunks(&self.data, entry.offset, entry.num_chunks) }
+    }
+}
+
+#[pymethods]
+impl RsTermSets {
+
+    // AND: documents containing every term in `terms`
+    fn documents_with_all(&self, terms: Vec<TokenId>) -> PyResult<Vec<DocumentId>> {
+        if terms.len() == 0 {
+            return Err(exceptions::ValueError::py_err("Terms cannot be empty"));
+        }
+        if self.debug {
+            eprintln!("documents_with_all: {:?}", terms);
+        }
+        let mut result: Option<RoaringBitmap> = None;
+        for term in &terms {
+            let bitmap = match self.terms.get(term) {
+                Some(entry) => self.read_bitmap(entry),
+                None => return Ok(vec![]), // Term never occurs, so the AND is empty
+            };
+            result = Some(match result {
+                None => bitmap,
+                Some(acc) => acc.and(&bitmap)
+            });
+        }
+        Ok(result.unwrap().to_vec())
+    }
+
+    // OR: documents containing any term in `terms`
+    fn documents_with_any(&self, terms: Vec<TokenId>) -> PyResult<Vec<DocumentId>> {
+        if terms.len() == 0 {
+            return Err(exceptions::ValueError::py_err("Terms cannot be empty"));
+        }
+        if self.debug {
+            eprintln!("documents_with_any: {:?}", terms);
+        }
+        let mut result: Option<RoaringBitmap> = None;
+        for term in &terms {
+            if let Some(entry) = self.terms.get(term) {
+                let bitmap = self.read_bitmap(entry);
+                result = Some(match result {
+                    None => bitmap,
+                    Some(acc) => acc.or(&bitmap)
+                });
+            }
+        }
+        Ok(result.map(|b| b.to_vec()).unwrap_or_else(Vec::new))
+    }
+
+    // NOT: documents in `doc_ids` (or, if empty, every document holding any indexed term)
+    // that do not contain `term`
+    fn documents_without(&self, term: TokenId, mut doc_ids: Vec<DocumentId>) ->
+                         PyResult<Vec<DocumentId>> {
+        if self.debug {
+            eprintln!("documents_without: {:?}", term);
+        }
+        let universe = if doc_ids.len() > 0 {
+            doc_ids.par_sort();
+            doc_ids.dedup();
+            RoaringBitmap::from_sorted_ids(&doc_ids)
+        } else {
+            self.universe.clone()
+        };
+        match self.terms.get(&term) {
+            Some(entry) => Ok(universe.and_not(&self.read_bitmap(entry)).to_vec()),
+            None => Ok(universe.to_vec())
+        }
+    }
+
+    #[new]
+    unsafe fn __new__(obj: &PyRawObject, bitmap_file: String, debug: bool) -> PyResult<()> {
+        let mmap = MmapOptions::new().map(&File::open(&bitmap_file)?);
+        match mmap {
+            Ok(m) => obj.init(|_| {
+                let token_count = read_mmap_u32(&m, 0) as usize;
+                let u32_size = mem::size_of::<u32>();
+                let mut terms = BTreeMap::new();
+                let mut universe = RoaringBitmap { chunks: vec![] };
+                for i in 0..token_count {
+                    let base = u32_size + i * 3 * u32_size;
+                    let token = read_mmap_u32(&m, base);
+                    let offset = read_mmap_u32(&m, base + u32_size) as usize;
+                    let num_chunks = read_mmap_u32(&m, base + 2 * u32_size) as usize;
+                    universe.chunks = or_chunks(
+                        &universe.chunks, &read_bitmap_chunks(&m, offset, num_chunks));
+                    terms.insert(token, TermSetEntry { offset: offset, num_chunks: num_chunks });
+                }
+                if debug {
+                    eprintln!("Loaded term sets for {} tokens", terms.len());
+                }
+                RsTermSets { terms: terms, data: m, universe: universe, debug: debug }
+            }),
+            Err(s) => Err(exceptions::Exception::py_err(s.to_string()))
+        }
+    }
 }
 
+// Levenshtein distance between `a` and `b`, bailing out early (returning `None`) as soon
+// as every cell in a row exceeds `max_edits` (no alignment can recover from that), so a
+// caller can cheaply reject most candidates well before the full table is filled.
+fn bounded_edit_distance<T: PartialEq>(a: &[T], b: &[T], max_edits: usize) -> Option<usize> {
+    let (n, m) = (a.len(), b.len());
+    if (n as i64 - m as i64).abs() as usize > max_edits {
+        return None;
+    }
+    let mut prev: Vec<usize> = (0..=m).collect();
+    let mut curr = vec![0usize; m + 1];
+    for i in 1..=n {
+        curr[0] = i;
+        let mut row_min = curr[0];
+        for j in 1..=m {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr[j] = cmp::min(cmp::min(prev[j] + 1, curr[j - 1] + 1), prev[j - 1] + cost);
+            row_min = cmp::min(row_min, curr[j]);
+        }
+        if row_min > max_edits {
+            return None;
+        }
+        mem::swap(&mut prev, &mut curr);
+    }
+    if prev[m] <= max_edits { Some(prev[m]) } else { None }
+}
+
+// Stages a term vocabulary (surface string -> TokenId) and serializes it into the sorted
+// directory + data layout `RsLexicon` loads: a `term_count`-u32 header, then one
+// `(token_id, byte_offset, byte_len)` directory entry per term ascending by term bytes,
+// then every term's UTF-8 bytes back to back.
 #[pyclass]
-struct RsMetadataIndex {
-    docs: BTreeMap<DocumentId, (usize, usize)>,  // Offset and length
+struct RsLexiconBuilder {
+    terms: BTreeMap<String, TokenId>,
+}
+
+#[pymethods]
+impl RsLexiconBuilder {
+
+    fn add_term(&mut self, term: String, token_id: TokenId) -> PyResult<()> {
+        self.terms.insert(term, token_id);
+        Ok(())
+    }
+
+    fn finish(&self, lexicon_file: String) -> PyResult<()> {
+        let directory_size = mem::size_of::<u32>() + self.terms.len() * 3 * mem::size_of::<u32>();
+        let mut directory = Vec::with_capacity(self.terms.len());
+        let mut data = vec![];
+        for (term, &token_id) in &self.terms {
+            let bytes = term.as_bytes();
+            let offset = directory_size + data.len();
+            data.extend_from_slice(bytes);
+            directory.push((token_id, offset as u32, bytes.len() as u32));
+        }
+
+        let mut buf = Vec::with_capacity(directory_size + data.len());
+        write_u32(&mut buf, self.terms.len() as u32);
+        for (token_id, offset, len) in directory {
+            write_u32(&mut buf, token_id);
+            write_u32(&mut buf, offset);
+            write_u32(&mut buf, len);
+        }
+        buf.extend(data);
+        File::create(&lexicon_file)?.write_all(&buf)?;
+        Ok(())
+    }
+
+    #[new]
+    unsafe fn __new__(obj: &PyRawObject) -> PyResult<()> {
+        obj.init(|_| RsLexiconBuilder { terms: BTreeMap::new() });
+        Ok(())
+    }
+}
+
+struct LexiconEntry {
+    token_id: TokenId,
+    offset: usize,
+    len: usize,
+}
+
+// Resolves surface strings to the token ids `RsCaptionIndex`'s search methods expect, via
+// a term vocabulary kept sorted by byte order on disk so prefix lookups can binary-search
+// straight against the mmap; fuzzy lookups scan every term.
+#[pyclass]
+struct RsLexicon {
+    entries: Vec<LexiconEntry>,
     data: Mmap,
+    debug: bool,
+}
+
+impl RsLexicon {
+    fn term_bytes(&self, i: usize) -> &[u8] {
+        let e = &self.entries[i];
+        &self.data[e.offset..e.offset + e.len]
+    }
+
+    // Smallest index whose term is >= `target`
+    fn lower_bound(&self, target: &[u8]) -> usize {
+        let mut lo = 0;
+        let mut hi = self.entries.len();
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if self.term_bytes(mid) < target {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+}
+
+#[pymethods]
+impl RsLexicon {
+
+    // Terms starting with `prefix`, located by binary-searching the sorted term bytes for
+    // the start of the range and scanning forward until the prefix no longer matches.
+    fn prefix_terms(&self, prefix: String) -> PyResult<Vec<(String, TokenId)>> {
+        if self.debug {
+            eprintln!("prefix_terms: {:?}", prefix);
+        }
+        let prefix_bytes = prefix.as_bytes();
+        let mut result = vec![];
+        for i in self.lower_bound(prefix_bytes)..self.entries.len() {
+            let term = self.term_bytes(i);
+            if !term.starts_with(prefix_bytes) {
+                break;
+            }
+            result.push((String::from_utf8_lossy(term).into_owned(), self.entries[i].token_id));
+        }
+        Ok(result)
+    }
+
+    // Terms within `max_edits` of `query`, as `(term, token_id, edit_distance)` triples.
+    // Distance is computed over decoded codepoints, not raw UTF-8 bytes, so a term whose
+    // byte length differs from the query's (e.g. via multi-byte characters) isn't scored
+    // incorrectly or pruned on a byte-length window that doesn't bound the real char-level
+    // edit distance.
+    //
+    // This is a linear scan of the vocabulary rather than a Levenshtein-automaton walk over
+    // an FST, which would let a query prune most of the term dictionary without visiting it.
+    // The scan is the pragmatic substitute already in place for `prefix_terms`'s binary
+    // search: fine for the vocabulary sizes this index targets, but it does mean fuzzy_terms
+    // is O(vocab) per query rather than near-O(max_edits). Revisit with a real FST term
+    // dictionary if vocabularies grow large enough for that to matter.
+    fn fuzzy_terms(&self, query: String, max_edits: usize) -> PyResult<Vec<(String, TokenId, usize)>> {
+        if self.debug {
+            eprintln!("fuzzy_terms: {:?} within {} edits", query, max_edits);
+        }
+        let query_chars: Vec<char> = query.chars().collect();
+
+        let mut result = vec![];
+        for (i, _) in self.entries.iter().enumerate() {
+            let term = self.term_bytes(i);
+            let term_str = String::from_utf8_lossy(term);
+            let term_chars: Vec<char> = term_str.chars().collect();
+            if (term_chars.len() as i64 - query_chars.len() as i64).abs() as usize > max_edits {
+                continue;
+            }
+            if let Some(dist) = bounded_edit_distance(&query_chars, &term_chars, max_edits) {
+                result.push((term_str.into_owned(), self.entries[i].token_id, dist));
+            }
+        }
+        Ok(result)
+    }
+
+    #[new]
+    unsafe fn __new__(obj: &PyRawObject, lexicon_file: String, debug: bool) -> PyResult<()> {
+        let mmap = MmapOptions::new().map(&File::open(&lexicon_file)?);
+        match mmap {
+            Ok(m) => obj.init(|_| {
+                let term_count = read_mmap_u32(&m, 0) as usize;
+                let u32_size = mem::size_of::<u32>();
+                let mut entries = Vec::with_capacity(term_count);
+                for i in 0..term_count {
+                    let base = u32_size + i * 3 * u32_size;
+                    let token_id = read_mmap_u32(&m, base);
+                    let offset = read_mmap_u32(&m, base + u32_size) as usize;
+                    let len = read_mmap_u32(&m, base + 2 * u32_size) as usize;
+                    entries.push(LexiconEntry { token_id: token_id, offset: offset, len: len });
+                }
+                if debug {
+                    eprintln!("Loaded lexicon with {} terms", entries.len());
+                }
+                RsLexicon { entries: entries, data: m, debug: debug }
+            }),
+            Err(s) => Err(exceptions::Exception::py_err(s.to_string()))
+        }
+    }
+}
+
+// Either a sparse per-document offset/length map (built for arbitrary id spaces) or a flat
+// table indexed directly by `DocumentId`, for dense corpora where a tree lookup per call is
+// wasted work. Both store the same offset a sparse entry would: the position of the
+// document's `(doc_id, n)` header, not the start of its data.
+enum DocOffsets {
+    Sparse(BTreeMap<DocumentId, (usize, usize)>),
+    // `u32::MAX` marks a hole. Only the header offset is kept per slot; the document's
+    // length is read lazily from the `n` word that follows it in the mmap, so growing this
+    // table costs no more than the sparse map's single `usize` of length would.
+    Dense(Vec<u32>),
+}
+
+// Storage codec for each document's entry block. `None` keeps the original zero-copy layout
+// (`PyBytes::new` borrows straight out of the mmap); `Zstd` trades that for a smaller file at
+// the cost of decompressing the touched document on every cache miss.
+#[derive(Clone, Copy, PartialEq)]
+enum Codec {
+    None,
+    Zstd,
+}
+
+impl Codec {
+    fn from_str(s: &str) -> PyResult<Codec> {
+        match s {
+            "none" => Ok(Codec::None),
+            "zstd" => Ok(Codec::Zstd),
+            _ => Err(exceptions::ValueError::py_err("Unknown codec (expected 'none' or 'zstd')"))
+        }
+    }
+}
+
+// The on-disk header preceding each document's block is `(doc_id, n)` for `Codec::None`, where
+// `n * entry_size` bytes of raw entries follow directly; for `Codec::Zstd` it gains a third
+// word, `compressed_len`, and the bytes that follow are a zstd frame which inflates to exactly
+// `n * entry_size` bytes.
+fn block_len(m: &Mmap, offset: usize, entry_size: usize, codec: Codec) -> usize {
+    let u32_size = mem::size_of::<u32>();
+    let n = read_mmap_u32(m, offset + u32_size) as usize;
+    match codec {
+        Codec::None => 2 * u32_size + n * entry_size,
+        Codec::Zstd => {
+            let compressed_len = read_mmap_u32(m, offset + 2 * u32_size) as usize;
+            3 * u32_size + compressed_len
+        }
+    }
+}
+
+fn parse_meta(m: &Mmap, entry_size: usize, dense: bool, codec: Codec, debug: bool) -> DocOffsets {
+    let meta_size = m.len();
+    let u32_size = mem::size_of::<u32>();
+    let mut curr_offset = 0;
+    if dense {
+        let mut offsets: Vec<u32> = vec![];
+        while curr_offset < meta_size {
+            let doc_id = read_mmap_u32(&m, curr_offset) as usize;
+            if doc_id >= offsets.len() {
+                offsets.resize(doc_id + 1, u32::max_value());
+            }
+            offsets[doc_id] = curr_offset as u32;
+            curr_offset += block_len(m, curr_offset, entry_size, codec);
+        }
+        if debug {
+            eprintln!("Loaded dense index spanning {} document ids", offsets.len());
+        }
+        assert!(curr_offset == meta_size, "Invalid number of bytes read");
+        DocOffsets::Dense(offsets)
+    } else {
+        let mut docs = BTreeMap::new();
+        while curr_offset < meta_size {
+            let doc_id = read_mmap_u32(&m, curr_offset) as DocumentId;
+            let n = read_mmap_u32(&m, curr_offset + u32_size) as usize;
+            docs.insert(doc_id, (curr_offset, n));
+            curr_offset += block_len(m, curr_offset, entry_size, codec);
+        }
+        if debug {
+            eprintln!("Loaded index containing {} documents", docs.len());
+        }
+        assert!(curr_offset == meta_size, "Invalid number of bytes read");
+        DocOffsets::Sparse(docs)
+    }
+}
+
+// Bounds the number of decompressed document blocks kept around at once, in FIFO eviction
+// order, so repeatedly-queried documents in a `Codec::Zstd` index skip re-inflating their
+// frame on every `metadata()` call. A `capacity` of zero disables caching entirely.
+struct DecompressionCache {
+    capacity: usize,
+    order: VecDeque<DocumentId>,
+    entries: BTreeMap<DocumentId, Vec<u8>>,
+}
+
+impl DecompressionCache {
+    fn new(capacity: usize) -> DecompressionCache {
+        DecompressionCache { capacity: capacity, order: VecDeque::new(), entries: BTreeMap::new() }
+    }
+
+    fn get(&self, doc_id: DocumentId) -> Option<Vec<u8>> {
+        self.entries.get(&doc_id).cloned()
+    }
+
+    fn insert(&mut self, doc_id: DocumentId, data: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if !self.entries.contains_key(&doc_id) {
+            if self.order.len() >= self.capacity {
+                if let Some(evicted) = self.order.pop_front() {
+                    self.entries.remove(&evicted);
+                }
+            }
+            self.order.push_back(doc_id);
+        }
+        self.entries.insert(doc_id, data);
+    }
+}
+
+#[pyclass]
+struct RsMetadataIndex {
+    // Held behind locks for the same reason as `_RsCaptionIndex::state`: `add_document`
+    // grows the backing file and needs to swap both in under the `data` writer, while
+    // `metadata()` only ever takes short-lived read guards per call.
+    docs: RwLock<DocOffsets>,
+    data: RwLock<Mmap>,
     entry_size: usize,
+    meta_file: String,
+    // Selects which `DocOffsets` variant `reload_from_disk` rebuilds after an append
+    dense: bool,
+    codec: Codec,
+    cache: RwLock<DecompressionCache>,
     debug: bool
 }
 
+impl RsMetadataIndex {
+    fn lookup(&self, doc_id: DocumentId, data: &Mmap) -> Option<(usize, usize)> {
+        match *self.docs.read().unwrap() {
+            DocOffsets::Sparse(ref docs) => docs.get(&doc_id).cloned(),
+            DocOffsets::Dense(ref offsets) => {
+                let idx = doc_id as usize;
+                match offsets.get(idx) {
+                    Some(&ofs) if ofs != u32::max_value() => {
+                        let n = read_mmap_u32(data, ofs as usize + mem::size_of::<u32>()) as usize;
+                        Some((ofs as usize, n))
+                    },
+                    _ => None
+                }
+            }
+        }
+    }
+
+    // Inflates the zstd frame at `doc_ofs` into a scratch `Vec<u8>`, reusing a cached copy
+    // when the document was decompressed recently. Only called for `Codec::Zstd` indexes.
+    fn decompress_doc(&self, doc_id: DocumentId, doc_ofs: usize, doc_len: usize, data: &Mmap)
+                      -> PyResult<Vec<u8>> {
+        if let Some(cached) = self.cache.read().unwrap().get(doc_id) {
+            return Ok(cached);
+        }
+        let u32_size = mem::size_of::<u32>();
+        let compressed_len = read_mmap_u32(data, doc_ofs + 2 * u32_size) as usize;
+        let start = doc_ofs + 3 * u32_size;
+        let compressed = &data[start..start + compressed_len];
+        let decompressed = zstd::decode_all(compressed)
+            .map_err(|e| exceptions::Exception::py_err(e.to_string()))?;
+        debug_assert_eq!(decompressed.len(), doc_len * self.entry_size);
+        self.cache.write().unwrap().insert(doc_id, decompressed.clone());
+        Ok(decompressed)
+    }
+}
+
 #[pymethods]
 impl RsMetadataIndex {
 
@@ -623,16 +2109,40 @@ impl RsMetadataIndex {
         if self.debug {
             eprintln!("Metdata: {}+{} in {}", position, position, doc_id);
         }
-        match self.docs.get(&doc_id) {
+        let data = self.data.read().unwrap();
+        match self.lookup(doc_id, &data) {
             Some((doc_ofs, doc_len)) => {
-                let mut result = vec![];
-                let max_idx = cmp::min(position + n, *doc_len);
-                let data = self.data[*doc_ofs..*doc_ofs + max_idx * self.entry_size].as_ref();
+                // Clamp rather than assume `position <= doc_len`: a caller-supplied `position`
+                // past the document's length must return an empty slice (matching
+                // `RsCaptionIndex::tokens`'s `min_pos` clamp), not underflow `max_idx - position`.
+                let position = cmp::min(position, doc_len);
+                let max_idx = cmp::min(position + n, doc_len);
                 let gil = Python::acquire_gil();
                 let py = gil.python();
-                for i in position..max_idx {
-                    let ofs = i * self.entry_size;
-                    result.push(PyBytes::new(py, &data[ofs..ofs + self.entry_size]));
+                let mut result = Vec::with_capacity(max_idx - position);
+                match self.codec {
+                    // Zero-copy fast path: borrow entries straight out of the mmap. Entries
+                    // start right after the `(doc_id, n)` header, same as the decompressed
+                    // bytes `Codec::Zstd` hands back below. Baseline's uncompressed path read
+                    // straight from `doc_ofs` (the header start) instead of skipping it, so it
+                    // was actually returning the header's bytes as bogus entry 0 and dropping
+                    // the document's last entry — a pre-existing bug, not a side effect of
+                    // adding zstd support, fixed here so both codecs return the same entries.
+                    Codec::None => {
+                        let entries_ofs = doc_ofs + 2 * mem::size_of::<u32>();
+                        let bytes = data[entries_ofs..entries_ofs + max_idx * self.entry_size].as_ref();
+                        for i in position..max_idx {
+                            let ofs = i * self.entry_size;
+                            result.push(PyBytes::new(py, &bytes[ofs..ofs + self.entry_size]));
+                        }
+                    },
+                    Codec::Zstd => {
+                        let bytes = self.decompress_doc(doc_id, doc_ofs, doc_len, &data)?;
+                        for i in position..max_idx {
+                            let ofs = i * self.entry_size;
+                            result.push(PyBytes::new(py, &bytes[ofs..ofs + self.entry_size]));
+                        }
+                    }
                 }
                 Ok(result)
             },
@@ -640,32 +2150,53 @@ impl RsMetadataIndex {
         }
     }
 
-    #[new]
-    unsafe fn __new__(obj: &PyRawObject, meta_file: String, entry_size: usize, debug: bool)
-                      -> PyResult<()> {
-        let parse_meta = |m: &Mmap| {
-            let mut docs = BTreeMap::new();
-            let meta_size = m.len();
-            let mut curr_offset = 0;
-            let u32_size = mem::size_of::<u32>();
-            while curr_offset < meta_size {
-                let doc_id = read_mmap_u32(&m, curr_offset) as DocumentId;
-                let n = read_mmap_u32(&m, curr_offset + u32_size) as usize;
-                docs.insert(doc_id, (curr_offset, n));
-                curr_offset += 2 * u32_size + n * entry_size;
-            }
-            if debug {
-                eprintln!("Loaded index containing {} documents", docs.len());
-            }
-            assert!(curr_offset == meta_size, "Invalid number of bytes read");
-            docs
-        };
+    // Appends a new document's metadata block to the backing file and refreshes the
+    // in-memory offset map, mirroring `RsCaptionIndex::add_document`.
+    fn add_document(&self, doc_id: DocumentId, data: Vec<u8>) -> PyResult<()> {
+        if data.len() % self.entry_size != 0 {
+            return Err(exceptions::ValueError::py_err(
+                "Data length is not a multiple of entry_size"));
+        }
+        let n = (data.len() / self.entry_size) as u32;
+        let mut buf = vec![];
+        write_u32(&mut buf, doc_id);
+        write_u32(&mut buf, n);
+        match self.codec {
+            Codec::None => buf.extend(data),
+            Codec::Zstd => {
+                let compressed = zstd::encode_all(data.as_slice(), 0)?;
+                write_u32(&mut buf, compressed.len() as u32);
+                buf.extend(compressed);
+            }
+        }
+        let mut f = OpenOptions::new().append(true).open(&self.meta_file)?;
+        f.write_all(&buf)?;
+        self.reload_from_disk()
+    }
+
+    // Drops the cached parsed state and re-maps `meta_file`, picking up any documents an
+    // external writer appended since construction (or since the last `reload`).
+    fn reload(&self) -> PyResult<()> {
+        self.reload_from_disk()
+    }
 
+    // Alias for `reload`, named for callers thinking of this as invalidating a stale cache.
+    fn invalidate(&self) -> PyResult<()> {
+        self.reload_from_disk()
+    }
+
+    #[new]
+    unsafe fn __new__(obj: &PyRawObject, meta_file: String, entry_size: usize, dense: bool,
+                      codec: String, cache_size: usize, debug: bool) -> PyResult<()> {
+        let codec = Codec::from_str(&codec)?;
         let mmap = MmapOptions::new().map(&File::open(&meta_file)?);
         match mmap {
             Ok(m) => obj.init(|_| {
+                let docs = parse_meta(&m, entry_size, dense, codec, debug);
                 RsMetadataIndex {
-                    docs: parse_meta(&m), data: m, entry_size: entry_size, debug: debug
+                    docs: RwLock::new(docs), data: RwLock::new(m), entry_size: entry_size,
+                    meta_file: meta_file, dense: dense, codec: codec,
+                    cache: RwLock::new(DecompressionCache::new(cache_size)), debug: debug
                 }
             }),
             Err(s) => Err(exceptions::Exception::py_err(s.to_string()))
@@ -673,9 +2204,37 @@ impl RsMetadataIndex {
     }
 }
 
+impl RsMetadataIndex {
+    fn reload_from_disk(&self) -> PyResult<()> {
+        let mmap = MmapOptions::new().map(&File::open(&self.meta_file)?);
+        match mmap {
+            Ok(m) => {
+                let docs = parse_meta(&m, self.entry_size, self.dense, self.codec, self.debug);
+                // Hold the `data` write guard across both swaps and the cache reset:
+                // `metadata()` takes `data` before `docs`, so it can never pair the new
+                // offsets with the stale mmap (or vice versa).
+                let mut data = self.data.write().unwrap();
+                *self.docs.write().unwrap() = docs;
+                *data = m;
+                // A reload can change where a `doc_id`'s block lives (or remove it), so any
+                // previously decompressed blocks must be dropped along with the old offsets.
+                let cache_size = self.cache.read().unwrap().capacity;
+                *self.cache.write().unwrap() = DecompressionCache::new(cache_size);
+                Ok(())
+            },
+            Err(s) => Err(exceptions::Exception::py_err(s.to_string()))
+        }
+    }
+}
+
 #[pymodinit]
 fn rs_captions(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<RsCaptionIndex>()?;
+    m.add_class::<RsCaptionIndexBuilder>()?;
+    m.add_class::<RsTermSets>()?;
+    m.add_class::<RsTermSetsBuilder>()?;
+    m.add_class::<RsLexicon>()?;
+    m.add_class::<RsLexiconBuilder>()?;
     m.add_class::<RsMetadataIndex>()?;
     Ok(())
 }
\ No newline at end of file